@@ -48,16 +48,41 @@ impl ReadReady for SynopsysUart<'_> {
 
 impl Read for SynopsysUart<'_> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // A zero-length read is always a no-op, even if an error is pending.
         if buf.is_empty() {
-            Ok(0)
-        } else {
-            // Wait until a byte is available to read.
-            loop {
-                // Read a single byte. No need to wait for more, the caller will retry until it has
-                // as many as it wants.
-                if let Some(byte) = self.read_word()? {
-                    buf[0] = byte;
-                    return Ok(1);
+            return Ok(0);
+        }
+
+        // An error from the previous call was deferred until the good bytes preceding it had been
+        // delivered; surface it now before reading anything new.
+        if let Some(error) = self.pending_read_error.take() {
+            return Err(error);
+        }
+
+        // Wait until at least one byte is available, then drain the FIFO into `buf` without
+        // blocking further.
+        let mut read = 0;
+        loop {
+            match self.read_word() {
+                Ok(Some(byte)) => {
+                    buf[read] = byte;
+                    read += 1;
+                    if read == buf.len() || self.is_rx_fifo_empty() {
+                        return Ok(read);
+                    }
+                }
+                Ok(None) => {
+                    if read > 0 {
+                        return Ok(read);
+                    }
+                }
+                Err(error) => {
+                    if read > 0 {
+                        // Return the good bytes now, and report the error on the next call.
+                        self.pending_read_error = Some(error);
+                        return Ok(read);
+                    }
+                    return Err(error);
                 }
             }
         }
@@ -12,28 +12,56 @@
 mod embedded_io;
 pub mod registers;
 
-use crate::registers::{Fcr, Lcr, Lsr, Registers, Usr};
-use core::{fmt, hint::spin_loop};
+use crate::registers::{Fcr, Ier, Lcr, Lsr, Mcr, Msr, Registers, Usr};
+use core::{fmt, hint::spin_loop, ptr::NonNull};
 use safe_mmio::{UniqueMmioPointer, field, field_shared};
 use thiserror::Error;
 
 /// Driver for a Synopsys DesignWare DW_apb UART.
 pub struct SynopsysUart<'a> {
     registers: UniqueMmioPointer<'a, Registers>,
+    /// The persistent bits (FIFO enable, DMA mode, and trigger levels) last written to `Fcr`.
+    ///
+    /// FCR is write-only, so this is the only way to read-modify-write it; without it, setting
+    /// the RX FIFO trigger level and enabling DMA mode would each silently clobber whatever the
+    /// other (or `configure`) had last written.
+    fcr: Fcr,
+    /// An error encountered while buffering bytes for a previous `embedded_io::Read::read` call,
+    /// to be returned from the next call rather than discarded.
+    #[cfg(feature = "embedded-io")]
+    pending_read_error: Option<UartError>,
 }
 
 impl<'a> SynopsysUart<'a> {
     /// Creates a new instance of the UART driver.
     pub const fn new(registers: UniqueMmioPointer<'a, Registers>) -> Self {
-        Self { registers }
+        Self {
+            registers,
+            fcr: Fcr::empty(),
+            #[cfg(feature = "embedded-io")]
+            pending_read_error: None,
+        }
     }
 
-    /// Configures the UART with the given baud rate, 8 data bits, no parity, and 1 stop bit.
+    /// Writes `bits` to FCR, replacing any previously-written bits covered by `mask` but
+    /// preserving other persistent bits that `mask` doesn't cover.
+    fn write_fcr(&mut self, mask: Fcr, bits: Fcr) {
+        self.fcr = (self.fcr & !mask) | bits;
+        field!(self.registers, iir_fcr).write(self.fcr.bits());
+    }
+
+    /// Configures the UART with the given baud rate and frame format.
     ///
     /// Also enables the transmit and receive FIFOs.
     ///
     /// This first waits until the UART is not busy, so may block.
-    pub fn configure(&mut self, baud_rate: u32, serial_clock: u32) {
+    ///
+    /// Returns the actually-programmed baud rate, which may differ slightly from `baud_rate` as
+    /// the fractional divisor can only represent a limited set of values; callers that need a
+    /// precise baud rate should check how close the result is to what they asked for.
+    pub fn configure(&mut self, baud_rate: u32, serial_clock: u32, line_config: LineConfig) -> u32 {
+        let dlf_width = self.capabilities().dlf_width;
+
         // Wait until the UART is not busy.
         while field_shared!(self.registers, usr)
             .read()
@@ -45,18 +73,41 @@ impl<'a> SynopsysUart<'a> {
         // Enable divisor latch access.
         field!(self.registers, lcr).write(Lcr::DLAB);
 
-        // Set the baud rate.
-        let divisor = serial_clock / (16 * baud_rate);
-        let fractional = (serial_clock % (16 * baud_rate)) / baud_rate;
+        // Compute the baud rate divisor as a fixed-point value with `dlf_width` fractional bits,
+        // rounded to the nearest representable value.
+        let scale = 1u64 << dlf_width;
+        let serial_clock = u64::from(serial_clock);
+        let baud_rate64 = u64::from(baud_rate);
+        let divisor_fp = (serial_clock * scale + 8 * baud_rate64) / (16 * baud_rate64);
+        let divisor = (divisor_fp / scale) as u32;
+        let fractional = (divisor_fp % scale) as u32 & ((1 << dlf_width) - 1);
+
         field!(self.registers, dlf).write(fractional);
         field!(self.registers, rbr_thr_dll).write(divisor & 0xff);
         field!(self.registers, dlh_ier).write(divisor >> 8);
 
-        // Configure 8N1 and disable divisor latch access.
-        field!(self.registers, lcr).write(Lcr::DLS_8);
+        // Configure the frame format and disable divisor latch access.
+        field!(self.registers, lcr).write(line_config.lcr_bits());
 
         // Enable TX and RX FIFOs.
-        field!(self.registers, iir_fcr).write(Fcr::FIFOE.bits());
+        self.write_fcr(Fcr::FIFOE, Fcr::FIFOE);
+
+        (serial_clock * scale / (16 * divisor_fp.max(1))) as u32
+    }
+
+    /// Queries the UART's hardware capabilities and configuration from the component parameter
+    /// and version registers.
+    pub fn capabilities(&self) -> Capabilities {
+        let cpr = field_shared!(self.registers, cpr).read();
+        let ucv = field_shared!(self.registers, ucv).read();
+        Capabilities {
+            fifo_depth: ((cpr >> 16) & 0xff) * 16,
+            auto_flow_control: cpr & (1 << 2) != 0,
+            sir: cpr & (1 << 4) != 0,
+            dma: cpr & (1 << 11) != 0,
+            dlf_width: (cpr >> 24) & 0xf,
+            version: ucv,
+        }
     }
 
     /// Returns whether the TX FIFO is currently full.
@@ -120,6 +171,150 @@ impl<'a> SynopsysUart<'a> {
             Ok(Some(field!(self.registers, rbr_thr_dll).read() as u8))
         }
     }
+
+    /// Enables the given interrupt sources, leaving any others unchanged.
+    pub fn enable_interrupts(&mut self, interrupts: Ier) {
+        let current = Ier::from_bits_truncate(field_shared!(self.registers, dlh_ier).read());
+        field!(self.registers, dlh_ier).write((current | interrupts).bits());
+    }
+
+    /// Disables the given interrupt sources, leaving any others unchanged.
+    pub fn disable_interrupts(&mut self, interrupts: Ier) {
+        let current = Ier::from_bits_truncate(field_shared!(self.registers, dlh_ier).read());
+        field!(self.registers, dlh_ier).write((current & !interrupts).bits());
+    }
+
+    /// Reads the cause of the highest-priority pending interrupt from the interrupt
+    /// identification register.
+    pub fn read_interrupt_cause(&self) -> InterruptType {
+        match field_shared!(self.registers, iir_fcr).read() & 0xf {
+            0b0000 => InterruptType::ModemStatus,
+            0b0010 => InterruptType::TransmitterHoldingRegisterEmpty,
+            0b0100 => InterruptType::ReceivedDataAvailable,
+            0b0110 => InterruptType::ReceiverLineStatus,
+            0b1100 => InterruptType::CharacterTimeout,
+            _ => InterruptType::None,
+        }
+    }
+
+    /// Enables hardware auto flow control.
+    ///
+    /// While enabled, the UART gates transmission on the peer asserting CTS, and deasserts RTS
+    /// once the RX FIFO nears full, relieving the driver of having to do so manually.
+    pub fn enable_auto_flow_control(&mut self) {
+        let current = field_shared!(self.registers, mcr).read();
+        field!(self.registers, mcr).write(current | Mcr::AFCE | Mcr::RTS);
+    }
+
+    /// Sets or clears the request-to-send (RTS) output.
+    ///
+    /// While auto flow control is enabled, the hardware drives RTS from the RX FIFO level only as
+    /// long as this bit is set (as `enable_auto_flow_control` does); clearing it keeps RTS
+    /// deasserted.
+    pub fn set_rts(&mut self, asserted: bool) {
+        let mut current = field_shared!(self.registers, mcr).read();
+        current.set(Mcr::RTS, asserted);
+        field!(self.registers, mcr).write(current);
+    }
+
+    /// Sets or clears the data-terminal-ready (DTR) output.
+    pub fn set_dtr(&mut self, asserted: bool) {
+        let mut current = field_shared!(self.registers, mcr).read();
+        current.set(Mcr::DTR, asserted);
+        field!(self.registers, mcr).write(current);
+    }
+
+    /// Returns the current state of the modem status inputs.
+    ///
+    /// Reading this register is impure: it also clears the delta bits (`DDCD`, `TERI`, `DDSR`,
+    /// `DCTS`) in `Msr`, so it takes `&mut self`.
+    pub fn modem_status(&mut self) -> Msr {
+        field!(self.registers, msr).read()
+    }
+
+    /// Sets the RX FIFO trigger level at which RTS is deasserted under auto flow control, using
+    /// one of the `Fcr::RT_*` constants.
+    ///
+    /// This also ensures the TX and RX FIFOs remain enabled.
+    pub fn set_rx_fifo_trigger(&mut self, trigger: Fcr) {
+        // `Fcr::RT_2_LESS` has both trigger-level bits set, so it doubles as the mask covering
+        // the whole field.
+        self.write_fcr(Fcr::FIFOE | Fcr::RT_2_LESS, Fcr::FIFOE | trigger);
+    }
+
+    /// Enables DMA mode, in which the UART signals the need for more data via DMA requests
+    /// rather than (or in addition to) interrupts.
+    ///
+    /// `mode` selects between the single- and multi-transaction DMA handshake schemes; also
+    /// ensures the TX and RX FIFOs remain enabled.
+    pub fn enable_dma_mode(&mut self, mode: DmaMode) {
+        let dmam = match mode {
+            DmaMode::Single => Fcr::empty(),
+            DmaMode::Multi => Fcr::DMAM,
+        };
+        self.write_fcr(Fcr::FIFOE | Fcr::DMAM, Fcr::FIFOE | dmam);
+    }
+
+    /// Returns the MMIO address of the combined receive-buffer/transmit-holding register, for
+    /// programming as the source (RX) or destination (TX) address of an external DMA engine.
+    pub fn dma_data_address(&mut self) -> NonNull<u32> {
+        field!(self.registers, rbr_thr_dll).ptr_nonnull().cast()
+    }
+
+    /// Returns the number of bytes currently in the transmit and receive FIFOs respectively, for
+    /// sizing DMA bursts.
+    pub fn fifo_levels(&self) -> (u32, u32) {
+        (
+            field_shared!(self.registers, tfl).read(),
+            field_shared!(self.registers, rfl).read(),
+        )
+    }
+
+    /// Returns the RX and TX FIFO trigger levels currently programmed, read back from the shadow
+    /// trigger registers since the FIFO control register itself is write-only.
+    pub fn fifo_trigger_levels(&self) -> (u32, u32) {
+        (
+            field_shared!(self.registers, srt).read(),
+            field_shared!(self.registers, stet).read(),
+        )
+    }
+
+    /// Issues a software DMA acknowledge.
+    ///
+    /// This is used with the single-transaction DMA handshake scheme, where the driver rather
+    /// than the DMA controller is responsible for acknowledging the end of a transfer.
+    pub fn dma_software_ack(&mut self) {
+        field!(self.registers, dmasa).write(1);
+    }
+
+    /// Forces the TX line to a break (logic 0) condition, or returns it to normal operation.
+    ///
+    /// This is a read-modify-write, so the rest of the frame format configuration is left
+    /// unchanged.
+    pub fn send_break(&mut self, enable: bool) {
+        let mut current = field_shared!(self.registers, lcr).read();
+        current.set(Lcr::BC, enable);
+        field!(self.registers, lcr).write(current);
+    }
+
+    /// Halts (or resumes) transmission from the TX FIFO, without affecting the break condition
+    /// set by `send_break`.
+    ///
+    /// This is useful to let already-queued bytes drain, or to hold off sending them, while
+    /// setting up a break condition.
+    pub fn set_halt_tx(&mut self, halt: bool) {
+        field!(self.registers, htx).write(halt.into());
+    }
+
+    /// Enables or disables loopback mode, in which the TX line is internally looped back to RX.
+    ///
+    /// This is useful for a power-on self-test, or for exercising `read_word`/`write_word`
+    /// without external hardware: a caller can write a byte and verify it reads back.
+    pub fn set_loopback(&mut self, enable: bool) {
+        let mut current = field_shared!(self.registers, mcr).read();
+        current.set(Mcr::LB, enable);
+        field!(self.registers, mcr).write(current);
+    }
 }
 
 // SAFETY: A `&SynopsysUart` only allows operations which read registers, which can safely be done
@@ -135,6 +330,115 @@ impl fmt::Write for SynopsysUart<'_> {
     }
 }
 
+/// Hardware capabilities and configuration reported by the component parameter register (`cpr`)
+/// and component version register (`ucv`).
+///
+/// These are synthesis-time parameters of the UART IP, so they are fixed for a given instance
+/// but vary between SoCs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Capabilities {
+    /// The depth of the TX and RX FIFOs, in bytes.
+    pub fifo_depth: u32,
+    /// Whether hardware auto flow control (`Mcr::AFCE`) is implemented.
+    pub auto_flow_control: bool,
+    /// Whether IrDA SIR mode is implemented.
+    pub sir: bool,
+    /// Whether DMA handshake signals are implemented.
+    pub dma: bool,
+    /// The width, in bits, of the fractional part of the baud rate divisor in the `dlf`
+    /// register.
+    pub dlf_width: u32,
+    /// The raw component version, as read from `ucv`.
+    pub version: u32,
+}
+
+/// The frame format to use for a [`SynopsysUart::configure`] call.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LineConfig {
+    /// The number of data bits per character, one of `Lcr::DLS_5` to `Lcr::DLS_8`.
+    pub data_bits: Lcr,
+    /// Whether to use an extra stop bit (1.5 stop bits for 5 data bits, 2 otherwise) rather than
+    /// a single stop bit.
+    pub extra_stop_bit: bool,
+    /// The parity mode to use.
+    pub parity: Parity,
+}
+
+impl LineConfig {
+    fn lcr_bits(self) -> Lcr {
+        let stop_bits = if self.extra_stop_bit {
+            Lcr::STOP
+        } else {
+            Lcr::empty()
+        };
+        self.data_bits | stop_bits | self.parity.lcr_bits()
+    }
+}
+
+impl Default for LineConfig {
+    /// 8 data bits, no parity, 1 stop bit.
+    fn default() -> Self {
+        Self {
+            data_bits: Lcr::DLS_8,
+            extra_stop_bit: false,
+            parity: Parity::None,
+        }
+    }
+}
+
+/// The parity mode for a UART frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Parity {
+    /// No parity bit.
+    None,
+    /// Odd parity: the parity bit is set so the total number of 1 bits, including it, is odd.
+    Odd,
+    /// Even parity: the parity bit is set so the total number of 1 bits, including it, is even.
+    Even,
+    /// Stick parity with the parity bit fixed to 1.
+    Mark,
+    /// Stick parity with the parity bit fixed to 0.
+    Space,
+}
+
+impl Parity {
+    fn lcr_bits(self) -> Lcr {
+        match self {
+            Self::None => Lcr::empty(),
+            Self::Odd => Lcr::PEN,
+            Self::Even => Lcr::PEN | Lcr::EPS,
+            Self::Mark => Lcr::PEN | Lcr::SP,
+            Self::Space => Lcr::PEN | Lcr::EPS | Lcr::SP,
+        }
+    }
+}
+
+/// The cause of a pending UART interrupt, decoded from the interrupt identification register.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InterruptType {
+    /// No interrupt is pending.
+    None,
+    /// The modem status register has changed.
+    ModemStatus,
+    /// The transmit holding register is empty and ready to accept more data.
+    TransmitterHoldingRegisterEmpty,
+    /// Data is available to be read from the receive FIFO.
+    ReceivedDataAvailable,
+    /// A receiver line status error (break, framing, parity or overrun) occurred.
+    ReceiverLineStatus,
+    /// The receive FIFO has stale data but hasn't reached its trigger level.
+    CharacterTimeout,
+}
+
+/// The DMA handshake signalling scheme selected by `Fcr::DMAM`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DmaMode {
+    /// A DMA request is issued for each byte transferred.
+    Single,
+    /// A DMA request covers a burst of several bytes, up to the programmed FIFO trigger level.
+    Multi,
+}
+
 /// A UART read error.
 #[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
 pub enum UartError {
@@ -19,6 +19,12 @@ bitflags! {
         const DLAB = 1 << 7;
         /// Break control bit.
         const BC = 1 << 6;
+        /// Stick parity.
+        ///
+        /// When parity is enabled, this forces the parity bit to a fixed value rather than
+        /// computing it from the data: combined with `EPS` this gives space parity, without it
+        /// mark parity.
+        const SP = 1 << 5;
         /// Even parity select.
         const EPS = 1 << 4;
         /// Parity enable.
@@ -41,6 +47,24 @@ impl Lcr {
     pub const DLS_8: Self = Self(0b11);
 }
 
+/// An interrupt enable register value (valid when DLAB is clear).
+#[derive(Copy, Clone, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
+#[repr(transparent)]
+pub struct Ier(u32);
+
+bitflags! {
+    impl Ier: u32 {
+        /// Enable modem status interrupt.
+        const EDSSI = 1 << 3;
+        /// Enable receiver line status interrupt.
+        const ELSI = 1 << 2;
+        /// Enable transmit holding register empty interrupt.
+        const ETBEI = 1 << 1;
+        /// Enable received data available interrupt.
+        const ERBFI = 1 << 0;
+    }
+}
+
 /// A modem control register value.
 #[derive(Copy, Clone, Debug, Eq, FromBytes, Immutable, IntoBytes, KnownLayout, PartialEq)]
 #[repr(transparent)]